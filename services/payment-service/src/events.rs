@@ -0,0 +1,131 @@
+// ============================================================
+// src/events.rs — structured payment-event stream
+// ============================================================
+// WHY?
+//   - Downstream analytics/fraud pipelines need a record of every payment
+//     lifecycle transition (created, authorized, completed, failed,
+//     refunded), not just the final row in our in-memory store.
+//   - The sink (Kafka today, something else tomorrow) must never stall the
+//     payment critical path: we hand events to a bounded tokio channel and a
+//     background task drains them. If the sink falls behind and the channel
+//     fills, we drop the event and count the drop rather than blocking the
+//     HTTP handler.
+//
+// The concrete sink is pluggable behind EventSink and selected from env,
+// following the event-stream pattern used by larger payment routers.
+
+use async_trait::async_trait;
+use prometheus::Counter;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+/// One payment lifecycle record.
+#[derive(Debug, Clone, Serialize)]
+pub struct PaymentEvent {
+    /// Transition name: created | authorized | completed | failed | refunded.
+    pub event:      String,
+    pub payment_id: String,
+    pub order_id:   i64,
+    pub user_id:    i64,
+    pub amount:     Decimal,
+    pub currency:   String,
+    pub status:     String,
+    pub provider:   String,
+    pub timestamp:  String,
+}
+
+// ── Sink trait + implementations ──────────────────────────────
+#[async_trait]
+pub trait EventSink: Send + Sync {
+    async fn emit(&self, event: &PaymentEvent);
+}
+
+/// Default sink: discards events. Used when no analytics backend is wired.
+pub struct NoopSink;
+
+#[async_trait]
+impl EventSink for NoopSink {
+    async fn emit(&self, _event: &PaymentEvent) {}
+}
+
+/// Publishes events to a Kafka topic via rdkafka.
+pub struct KafkaSink {
+    producer: rdkafka::producer::FutureProducer,
+    topic:    String,
+}
+
+impl KafkaSink {
+    fn from_env() -> Self {
+        use rdkafka::config::ClientConfig;
+        let brokers = std::env::var("KAFKA_BROKERS").unwrap_or_else(|_| "localhost:9092".to_string());
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", &brokers)
+            .set("message.timeout.ms", "5000")
+            .create()
+            .expect("failed to create Kafka producer");
+        KafkaSink {
+            producer,
+            topic: std::env::var("KAFKA_TOPIC").unwrap_or_else(|_| "payment-events".to_string()),
+        }
+    }
+}
+
+#[async_trait]
+impl EventSink for KafkaSink {
+    async fn emit(&self, event: &PaymentEvent) {
+        use rdkafka::producer::FutureRecord;
+        let payload = match serde_json::to_string(event) {
+            Ok(p) => p,
+            Err(e) => {
+                log::error!("[events] failed to serialize event: {e}");
+                return;
+            }
+        };
+        let key = event.payment_id.clone();
+        let record = FutureRecord::to(&self.topic).payload(&payload).key(&key);
+        if let Err((e, _)) = self.producer.send(record, std::time::Duration::from_secs(5)).await {
+            log::error!("[events] Kafka publish failed: {e}");
+        }
+    }
+}
+
+/// Resolve the sink from env: EVENT_SINK=kafka enables Kafka, anything else
+/// (or unset) uses the no-op sink.
+fn sink_from_env() -> Box<dyn EventSink> {
+    match std::env::var("EVENT_SINK").unwrap_or_default().to_lowercase().as_str() {
+        "kafka" => Box::new(KafkaSink::from_env()),
+        _ => Box::new(NoopSink),
+    }
+}
+
+// ── Non-blocking publisher ────────────────────────────────────
+/// Front door used by handlers: a cheap `publish` that never awaits the sink.
+#[derive(Clone)]
+pub struct EventPublisher {
+    tx:      mpsc::Sender<PaymentEvent>,
+    dropped: Counter,
+}
+
+impl EventPublisher {
+    /// Build a publisher backed by the env-selected sink and spawn the drain
+    /// task. `dropped` counts events shed when the channel is full.
+    pub fn start(capacity: usize, dropped: Counter) -> Self {
+        let (tx, mut rx) = mpsc::channel::<PaymentEvent>(capacity);
+        let sink = sink_from_env();
+        tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                sink.emit(&event).await;
+            }
+        });
+        EventPublisher { tx, dropped }
+    }
+
+    /// Enqueue an event without blocking. Drops (and counts) the event if the
+    /// channel is full so a slow sink never stalls the payment path.
+    pub fn publish(&self, event: PaymentEvent) {
+        if self.tx.try_send(event).is_err() {
+            self.dropped.inc();
+        }
+    }
+}
@@ -0,0 +1,124 @@
+// ============================================================
+// src/idempotency.rs — Idempotency-Key support for POST /payments
+// ============================================================
+// WHY?
+//   - A network retry of POST /payments must not charge the customer twice.
+//     Clients send a stable `Idempotency-Key` header; we remember the result
+//     we produced for a given (user_id, key) and replay it verbatim instead
+//     of calling the provider again.
+//   - Concurrent in-flight requests sharing a key must *serialize* so only a
+//     single provider call happens: the losers wait on the same per-key gate
+//     and then observe the stored result.
+//   - Keys expire after a configurable TTL so the map does not grow forever.
+//
+// Generic over the stored value so the store itself knows nothing about the
+// Payment type.
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+struct Cached<T> {
+    value:      T,
+    expires_at: DateTime<Utc>,
+}
+
+/// A per-key gate. Holding its mutex serializes concurrent requests for the
+/// same key; the inner Option caches the result once produced.
+pub struct Entry<T> {
+    gate:       Mutex<Option<Cached<T>>>,
+    ttl:        Duration,
+    /// When the entry was created, so an entry that never produced a result
+    /// (e.g. the provider call errored before `set`) can still be swept.
+    created_at: DateTime<Utc>,
+}
+
+impl<T: Clone> Entry<T> {
+    /// Take the gate for this key. The returned guard serializes all callers
+    /// for the key until it is dropped.
+    pub async fn enter(&self) -> EntryGuard<'_, T> {
+        EntryGuard { guard: self.gate.lock().await, ttl: self.ttl }
+    }
+
+    /// True once the entry is past its TTL: either the cached result has
+    /// expired, or it never produced one within the TTL window. Returns false
+    /// while another request holds the gate, so in-flight work is never swept.
+    fn is_expired(&self) -> bool {
+        match self.gate.try_lock() {
+            Ok(g) => match g.as_ref() {
+                Some(c) => c.expires_at <= Utc::now(),
+                None => self.created_at + self.ttl <= Utc::now(),
+            },
+            Err(_) => false,
+        }
+    }
+}
+
+/// Guard held while a key is being processed.
+pub struct EntryGuard<'a, T> {
+    guard: tokio::sync::MutexGuard<'a, Option<Cached<T>>>,
+    ttl:   Duration,
+}
+
+impl<T: Clone> EntryGuard<'_, T> {
+    /// The previously stored result for this key, if present and not expired.
+    pub fn get(&self) -> Option<T> {
+        self.guard
+            .as_ref()
+            .filter(|c| c.expires_at > Utc::now())
+            .map(|c| c.value.clone())
+    }
+
+    /// Store the result produced for this key.
+    pub fn set(&mut self, value: T) {
+        *self.guard = Some(Cached { value, expires_at: Utc::now() + self.ttl });
+    }
+}
+
+pub struct IdempotencyStore<T> {
+    ttl:     Duration,
+    entries: Mutex<HashMap<(i64, String), Arc<Entry<T>>>>,
+}
+
+impl<T: Clone> IdempotencyStore<T> {
+    pub fn from_env() -> Self {
+        let secs = std::env::var("IDEMPOTENCY_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .unwrap_or(86_400); // 24h
+        IdempotencyStore {
+            ttl:     Duration::seconds(secs),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Look up (or create) the gate for `(user_id, key)`, evicting any expired
+    /// entry first. Callers hold the returned Arc while they `enter()` it, so
+    /// the gate outlives the borrow.
+    pub async fn gate(&self, user_id: i64, key: &str) -> Arc<Entry<T>> {
+        let mut map = self.entries.lock().await;
+        let k = (user_id, key.to_string());
+        if let Some(existing) = map.get(&k) {
+            // Evict if the stored result has expired so retries after the TTL
+            // behave like a fresh request.
+            if !existing.is_expired() {
+                return Arc::clone(existing);
+            }
+        }
+        let entry = Arc::new(Entry {
+            gate:       Mutex::new(None),
+            ttl:        self.ttl,
+            created_at: Utc::now(),
+        });
+        map.insert(k, Arc::clone(&entry));
+        entry
+    }
+
+    /// Drop every entry past its TTL. Called periodically so keys that are
+    /// never retried do not accumulate forever on a hot payment path.
+    pub async fn sweep(&self) {
+        let mut map = self.entries.lock().await;
+        map.retain(|_, entry| !entry.is_expired());
+    }
+}
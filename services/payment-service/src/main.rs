@@ -11,17 +11,34 @@
 // This service simulates payment processing — in a real system it would
 // call Stripe/PayPal APIs. Secrets (API keys) come from Vault Agent injection.
 
+mod events;
+mod idempotency;
+mod money;
+mod provider;
+mod token;
+
 use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer, middleware};
 use chrono::Utc;
 use jsonwebtoken::{decode, DecodingKey, Validation, Algorithm};
 use log::{error, info};
 use prometheus::{Counter, Encoder, Opts, Registry, TextEncoder};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
+use events::{EventPublisher, PaymentEvent};
+use idempotency::IdempotencyStore;
+use provider::{AuthorizeRequest, PaymentProvider};
+
+// The selected payment connector, shared across requests.
+type Provider = Arc<dyn PaymentProvider>;
+
+// Replay cache for Idempotency-Key, shared across requests.
+type Idempotency = Arc<IdempotencyStore<Payment>>;
+
 // ── Domain Types ──────────────────────────────────────────────
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -29,22 +46,33 @@ struct Payment {
     id:         String,
     order_id:   i64,
     user_id:    i64,
-    amount:     f64,
+    // Always serialized/deserialized as a string so the value never passes
+    // through serde_json's f64 path and loses cents.
+    #[serde(with = "rust_decimal::serde::str")]
+    amount:     Decimal,
     currency:   String,
     status:     String,  // pending | completed | failed | refunded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reference:  Option<String>, // provider-side reference, used for webhook lookup
     created_at: String,
 }
 
 #[derive(Debug, Deserialize)]
 struct PaymentRequest {
     order_id: i64,
-    amount:   f64,
+    // Require a string-encoded amount (e.g. "12.34"); a bare JSON number would
+    // be parsed via f64 and silently lose precision on the payment path.
+    #[serde(with = "rust_decimal::serde::str")]
+    amount:   Decimal,
     currency: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct Claims {
-    sub: i64,  // user_id from user-service JWT
+    sub:  i64,             // user_id from user-service JWT
+    exp:  usize,           // expiry (seconds since epoch), enforced below
+    #[serde(default)]
+    role: Option<String>,  // e.g. "admin"; absent for ordinary customers
 }
 
 // ── Application State ─────────────────────────────────────────
@@ -53,7 +81,11 @@ struct Claims {
 type PaymentsStore = Arc<Mutex<Vec<Payment>>>;
 
 // ── JWT Auth Extractor ────────────────────────────────────────
-fn extract_user_id(req: &HttpRequest) -> Result<i64, String> {
+// Verify the HS256 signature against JWT_SECRET, enforce `exp`, and require
+// the `sub` claim. Signature verification can only be disabled by explicitly
+// setting JWT_INSECURE=true (for local dev); it is enforced otherwise so a
+// forged token is rejected in every normal deployment.
+fn extract_claims(req: &HttpRequest) -> Result<Claims, String> {
     let auth = req
         .headers()
         .get("Authorization")
@@ -66,8 +98,23 @@ fn extract_user_id(req: &HttpRequest) -> Result<i64, String> {
 
     let token = &auth[7..];
     let secret = env::var("JWT_SECRET").unwrap_or_default();
+    let insecure = env::var("JWT_INSECURE").map(|v| v == "true").unwrap_or(false);
+
+    // Fail closed: an empty secret would make HS256 verify against an empty
+    // key, letting anyone self-sign an admin token. Only the explicit insecure
+    // dev mode is allowed to proceed without a secret.
+    if secret.is_empty() && !insecure {
+        return Err("Server misconfigured: JWT_SECRET is not set".to_string());
+    }
+
     let mut validation = Validation::new(Algorithm::HS256);
-    validation.insecure_disable_signature_validation(); // use in dev only
+    validation.set_required_spec_claims(&["exp", "sub"]);
+
+    if insecure {
+        // Dev-only escape hatch; must be opted into explicitly.
+        validation.insecure_disable_signature_validation();
+        validation.validate_exp = false;
+    }
 
     let data = decode::<Claims>(
         token,
@@ -76,7 +123,37 @@ fn extract_user_id(req: &HttpRequest) -> Result<i64, String> {
     )
     .map_err(|e| format!("Invalid token: {e}"))?;
 
-    Ok(data.claims.sub)
+    Ok(data.claims)
+}
+
+fn extract_user_id(req: &HttpRequest) -> Result<i64, String> {
+    extract_claims(req).map(|c| c.sub)
+}
+
+/// Authenticate the caller and require that their token carries `role`.
+/// Used to gate privileged (admin-style) operations such as refunds so a
+/// customer JWT cannot trigger them.
+fn require_role(req: &HttpRequest, role: &str) -> Result<i64, String> {
+    let claims = extract_claims(req)?;
+    match claims.role.as_deref() {
+        Some(r) if r == role => Ok(claims.sub),
+        _ => Err(format!("Requires '{role}' role")),
+    }
+}
+
+// Build a lifecycle event from a payment snapshot.
+fn payment_event(event: &str, p: &Payment, provider: &str) -> PaymentEvent {
+    PaymentEvent {
+        event:      event.to_string(),
+        payment_id: p.id.clone(),
+        order_id:   p.order_id,
+        user_id:    p.user_id,
+        amount:     p.amount,
+        currency:   p.currency.clone(),
+        status:     p.status.clone(),
+        provider:   provider.to_string(),
+        timestamp:  Utc::now().to_rfc3339(),
+    }
 }
 
 // ── Handlers ──────────────────────────────────────────────────
@@ -104,6 +181,9 @@ async fn create_payment(
     payload: web::Json<PaymentRequest>,
     store: web::Data<PaymentsStore>,
     counter: web::Data<Counter>,
+    provider: web::Data<Provider>,
+    idem: web::Data<Idempotency>,
+    publisher: web::Data<EventPublisher>,
 ) -> HttpResponse {
     counter.inc();
 
@@ -113,30 +193,90 @@ async fn create_payment(
         Err(e) => return HttpResponse::Unauthorized().json(serde_json::json!({"error": e})),
     };
 
-    // Validate amount
-    if payload.amount <= 0.0 {
-        return HttpResponse::BadRequest()
-            .json(serde_json::json!({"error": "Amount must be positive"}));
+    let currency = payload.currency.clone().unwrap_or_else(|| "USD".to_string());
+
+    // Validate amount: positive and within the currency's minor-unit precision.
+    if let Err(e) = money::validate_amount(payload.amount, &currency) {
+        return HttpResponse::BadRequest().json(serde_json::json!({"error": e}));
+    }
+
+    // Idempotency-Key: serialize on the key and replay a prior result so a
+    // retry never produces a second charge. The gate is held across the
+    // provider call below, so concurrent requests with the same key wait.
+    let idem_key = req
+        .headers()
+        .get("Idempotency-Key")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let gate = match &idem_key {
+        Some(k) => Some(idem.gate(user_id, k).await),
+        None => None,
+    };
+    let mut guard = match &gate {
+        Some(g) => Some(g.enter().await),
+        None => None,
+    };
+    if let Some(existing) = guard.as_ref().and_then(|g| g.get()) {
+        return HttpResponse::Ok().json(existing);
     }
 
+    // Tell the provider where to call back asynchronously with the final
+    // status and where to return the customer afterwards.
+    let base = env::var("PUBLIC_BASE_URL").unwrap_or_else(|_| "http://localhost:8090".to_string());
+
+    // Hand the charge to the selected connector and map its result into our
+    // status vocabulary instead of faking "completed".
+    let authorize = AuthorizeRequest {
+        order_id: payload.order_id,
+        user_id,
+        amount:   payload.amount,
+        currency: currency.clone(),
+        notify_uri:   format!("{base}/payments/webhook"),
+        continue_uri: format!("{base}/orders/{}", payload.order_id),
+    };
+    let (status, reference) = match provider.authorize(&authorize).await {
+        Ok(resp) => (resp.status, resp.reference),
+        Err(e) => {
+            error!("[payment] provider {} authorize failed: {e}", provider.name());
+            return HttpResponse::BadGateway()
+                .json(serde_json::json!({"error": "Payment provider error"}));
+        }
+    };
+
     let payment = Payment {
         id:         Uuid::new_v4().to_string(),
         order_id:   payload.order_id,
         user_id,
         amount:     payload.amount,
-        currency:   payload.currency.clone().unwrap_or_else(|| "USD".to_string()),
-        // Simulate: in prod this would call the payment provider and return their status
-        status:     "completed".to_string(),
+        currency,
+        status,
+        reference,
         created_at: Utc::now().to_rfc3339(),
     };
 
     info!(
-        "[payment] Created payment {} for order {} amount {:.2}",
+        "[payment] Created payment {} for order {} amount {}",
         payment.id, payment.order_id, payment.amount
     );
 
-    let mut store = store.lock().await;
-    store.push(payment.clone());
+    {
+        let mut store = store.lock().await;
+        store.push(payment.clone());
+    }
+
+    // Emit lifecycle events on the non-blocking path: the order was created,
+    // then the provider's authorization outcome.
+    publisher.publish(payment_event("created", &payment, provider.name()));
+    let outcome = match payment.status.as_str() {
+        "pending" => "authorized",
+        other => other,
+    };
+    publisher.publish(payment_event(outcome, &payment, provider.name()));
+
+    // Remember the result so a retry with the same Idempotency-Key replays it.
+    if let Some(g) = guard.as_mut() {
+        g.set(payment.clone());
+    }
 
     HttpResponse::Created().json(payment)
 }
@@ -155,6 +295,190 @@ async fn list_payments(
     HttpResponse::Ok().json(user_payments)
 }
 
+// ── Refund (admin only) ───────────────────────────────────────
+// Privileged operation: only a token carrying the "admin" role may refund a
+// payment, so a customer JWT cannot trigger it. Delegates the actual reversal
+// to the selected connector.
+async fn refund_payment(
+    req: HttpRequest,
+    path: web::Path<String>,
+    store: web::Data<PaymentsStore>,
+    provider: web::Data<Provider>,
+    publisher: web::Data<EventPublisher>,
+) -> HttpResponse {
+    if let Err(e) = require_role(&req, "admin") {
+        return HttpResponse::Forbidden().json(serde_json::json!({"error": e}));
+    }
+
+    let payment_id = path.into_inner();
+
+    // Resolve the provider reference up front without holding the lock across
+    // the network call.
+    let reference = {
+        let store = store.lock().await;
+        match store.iter().find(|p| p.id == payment_id) {
+            Some(p) if !transition_allowed(&p.status, "refunded") => {
+                return HttpResponse::Conflict().json(serde_json::json!({
+                    "error": format!("Cannot refund a {} payment", p.status)
+                }));
+            }
+            Some(p) => match &p.reference {
+                Some(r) => r.clone(),
+                None => {
+                    return HttpResponse::UnprocessableEntity()
+                        .json(serde_json::json!({"error": "Payment has no provider reference"}));
+                }
+            },
+            None => {
+                return HttpResponse::NotFound()
+                    .json(serde_json::json!({"error": "Payment not found"}));
+            }
+        }
+    };
+
+    if let Err(e) = provider.refund(&reference).await {
+        error!("[payment] provider {} refund failed: {e}", provider.name());
+        return HttpResponse::BadGateway()
+            .json(serde_json::json!({"error": "Payment provider error"}));
+    }
+
+    let mut store = store.lock().await;
+    let Some(payment) = store.iter_mut().find(|p| p.id == payment_id) else {
+        return HttpResponse::NotFound().json(serde_json::json!({"error": "Payment not found"}));
+    };
+    payment.status = "refunded".to_string();
+    info!("[payment] Refunded payment {}", payment.id);
+
+    let event = payment_event("refunded", payment, provider.name());
+    publisher.publish(event);
+
+    HttpResponse::Ok().json(payment.clone())
+}
+
+// ── Provider webhook ──────────────────────────────────────────
+// Providers confirm or fail a payment out-of-band by POSTing to the
+// notify_uri we handed them. We authenticate the callback with an HMAC-SHA256
+// signature over the raw body (constant-time compared against a shared
+// secret), locate the referenced payment, and transition its status.
+
+#[derive(Debug, Deserialize)]
+struct WebhookNotification {
+    /// Provider-side reference, as returned from authorize().
+    #[serde(default)]
+    reference: Option<String>,
+    /// Fallback lookup key when the provider echoes our order id.
+    #[serde(default)]
+    order_id:  Option<i64>,
+    /// Resulting status in the provider's own words; mapped below.
+    status:    String,
+}
+
+/// Map an incoming notification status onto our vocabulary.
+fn map_webhook_status(raw: &str) -> Option<&'static str> {
+    match raw.to_lowercase().as_str() {
+        "completed" | "success" | "succeeded" | "captured" => Some("completed"),
+        "failed" | "declined" | "canceled" | "cancelled" => Some("failed"),
+        "refunded" | "refund" => Some("refunded"),
+        _ => None,
+    }
+}
+
+/// Only a subset of transitions are legal; callers reject anything else with
+/// 409 Conflict so a replayed or out-of-order callback cannot, say,
+/// un-refund a payment.
+fn transition_allowed(from: &str, to: &str) -> bool {
+    matches!(
+        (from, to),
+        ("pending", "completed") | ("pending", "failed") | ("completed", "refunded")
+    )
+}
+
+async fn provider_webhook(
+    req: HttpRequest,
+    body: web::Bytes,
+    store: web::Data<PaymentsStore>,
+    webhook_counter: web::Data<prometheus::CounterVec>,
+    provider: web::Data<Provider>,
+    publisher: web::Data<EventPublisher>,
+) -> HttpResponse {
+    // Verify the HMAC signature over the raw body before trusting anything.
+    // Fail closed: with an empty secret the HMAC would validate against an
+    // empty key and let anyone forge a status transition.
+    let secret = env::var("WEBHOOK_SECRET").unwrap_or_default();
+    if secret.is_empty() {
+        error!("[payment] WEBHOOK_SECRET is not set; rejecting webhook");
+        return HttpResponse::ServiceUnavailable()
+            .json(serde_json::json!({"error": "Webhook verification unavailable"}));
+    }
+    let signature = req
+        .headers()
+        .get("X-Signature")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    if !verify_webhook_signature(secret.as_bytes(), &body, signature) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({"error": "Invalid signature"}));
+    }
+
+    let notification: WebhookNotification = match serde_json::from_slice(&body) {
+        Ok(n) => n,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({"error": format!("Bad body: {e}")}))
+        }
+    };
+
+    let Some(target) = map_webhook_status(&notification.status) else {
+        return HttpResponse::BadRequest()
+            .json(serde_json::json!({"error": "Unknown status"}));
+    };
+
+    let mut store = store.lock().await;
+    let payment = store.iter_mut().find(|p| {
+        match (&notification.reference, notification.order_id) {
+            (Some(r), _) => p.reference.as_deref() == Some(r.as_str()),
+            (None, Some(oid)) => p.order_id == oid,
+            _ => false,
+        }
+    });
+
+    let payment = match payment {
+        Some(p) => p,
+        None => return HttpResponse::NotFound().json(serde_json::json!({"error": "Payment not found"})),
+    };
+
+    if !transition_allowed(&payment.status, target) {
+        return HttpResponse::Conflict().json(serde_json::json!({
+            "error": format!("Illegal transition {} -> {target}", payment.status)
+        }));
+    }
+
+    payment.status = target.to_string();
+    webhook_counter.with_label_values(&[target]).inc();
+    info!("[payment] Webhook transitioned payment {} to {target}", payment.id);
+
+    // Emit the transition on the non-blocking events path.
+    let event = payment_event(target, payment, provider.name());
+    publisher.publish(event);
+
+    HttpResponse::Ok().json(serde_json::json!({"status": payment.status}))
+}
+
+/// Constant-time verification of an HMAC-SHA256 signature, supplied as a
+/// lowercase hex string, over `body`.
+fn verify_webhook_signature(secret: &[u8], body: &[u8], signature_hex: &str) -> bool {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    let Ok(expected) = hex::decode(signature_hex) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret) else {
+        return false;
+    };
+    mac.update(body);
+    // verify_slice performs a constant-time comparison.
+    mac.verify_slice(&expected).is_ok()
+}
+
 // ── Main ──────────────────────────────────────────────────────
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -164,6 +488,16 @@ async fn main() -> std::io::Result<()> {
     let port = env::var("PORT").unwrap_or_else(|_| "8090".to_string());
     let addr = format!("0.0.0.0:{port}");
 
+    // Refuse to start with an empty JWT secret unless the insecure dev mode is
+    // explicitly requested — otherwise HS256 would verify against an empty key.
+    let jwt_insecure = env::var("JWT_INSECURE").map(|v| v == "true").unwrap_or(false);
+    if env::var("JWT_SECRET").unwrap_or_default().is_empty() && !jwt_insecure {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "JWT_SECRET must be set (or set JWT_INSECURE=true for local dev)",
+        ));
+    }
+
     // Prometheus registry
     let registry = Registry::new();
     let payment_counter = Counter::with_opts(
@@ -171,9 +505,47 @@ async fn main() -> std::io::Result<()> {
     ).unwrap();
     registry.register(Box::new(payment_counter.clone())).unwrap();
 
+    // Webhook outcomes, labeled by the status they transitioned the payment to.
+    let webhook_counter = prometheus::CounterVec::new(
+        Opts::new("payment_service_webhooks_total", "Provider webhooks by resulting status"),
+        &["status"],
+    ).unwrap();
+    registry.register(Box::new(webhook_counter.clone())).unwrap();
+
+    // Events dropped when the analytics channel is full (slow/absent sink).
+    let dropped_events = Counter::with_opts(
+        Opts::new("payment_service_events_dropped_total", "Payment events dropped due to a full channel")
+    ).unwrap();
+    registry.register(Box::new(dropped_events.clone())).unwrap();
+
     // Shared in-memory store
     let store: PaymentsStore = Arc::new(Mutex::new(Vec::new()));
 
+    // Resolve the payment connector once at startup (PAYMENT_PROVIDER).
+    let payment_provider: Provider = Arc::from(provider::from_env());
+    info!("[payment-service] Using payment provider '{}'", payment_provider.name());
+
+    // Idempotency replay cache, with a background sweeper so expired keys do
+    // not accumulate on the payment path.
+    let idempotency: Idempotency = Arc::new(IdempotencyStore::from_env());
+    {
+        let idempotency = idempotency.clone();
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(std::time::Duration::from_secs(300));
+            loop {
+                tick.tick().await;
+                idempotency.sweep().await;
+            }
+        });
+    }
+
+    // Analytics event publisher (bounded channel + env-selected sink).
+    let event_capacity = env::var("EVENT_CHANNEL_CAPACITY")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(1024);
+    let publisher = EventPublisher::start(event_capacity, dropped_events);
+
     info!("[payment-service] Listening on {addr}");
 
     HttpServer::new(move || {
@@ -182,14 +554,223 @@ async fn main() -> std::io::Result<()> {
             .app_data(web::Data::new(store.clone()))
             .app_data(web::Data::new(registry.clone()))
             .app_data(web::Data::new(payment_counter.clone()))
+            .app_data(web::Data::new(webhook_counter.clone()))
+            .app_data(web::Data::new(payment_provider.clone()))
+            .app_data(web::Data::new(idempotency.clone()))
+            .app_data(web::Data::new(publisher.clone()))
             // Platform routes
             .route("/health", web::get().to(health))
             .route("/metrics", web::get().to(metrics_handler))
             // Business routes
             .route("/payments", web::post().to(create_payment))
             .route("/payments", web::get().to(list_payments))
+            .route("/payments/{id}/refund", web::post().to(refund_payment))
+            .route("/payments/webhook", web::post().to(provider_webhook))
     })
     .bind(&addr)?
     .run()
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use rust_decimal_macros::dec;
+
+    const JWT: &str = "test-jwt-secret";
+    const HOOK: &str = "test-hook-secret";
+
+    fn token(sub: i64, role: Option<&str>) -> String {
+        let claims = serde_json::json!({ "sub": sub, "exp": 9_999_999_999i64, "role": role });
+        encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(JWT.as_bytes()),
+        )
+        .unwrap()
+    }
+
+    fn sign(body: &[u8]) -> String {
+        use hmac::{Hmac, Mac};
+        use sha2::Sha256;
+        let mut mac = Hmac::<Sha256>::new_from_slice(HOOK.as_bytes()).unwrap();
+        mac.update(body);
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    fn counter(name: &str) -> Counter {
+        Counter::with_opts(Opts::new(name, "test")).unwrap()
+    }
+
+    fn seed(payment: Payment) -> PaymentsStore {
+        Arc::new(Mutex::new(vec![payment]))
+    }
+
+    fn sample(status: &str) -> Payment {
+        Payment {
+            id:         "p1".to_string(),
+            order_id:   7,
+            user_id:    42,
+            amount:     dec!(12.34),
+            currency:   "USD".to_string(),
+            status:     status.to_string(),
+            reference:  Some("r1".to_string()),
+            created_at: "2026-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn webhook_status_and_transitions() {
+        assert_eq!(map_webhook_status("SUCCESS"), Some("completed"));
+        assert_eq!(map_webhook_status("declined"), Some("failed"));
+        assert_eq!(map_webhook_status("mystery"), None);
+        assert!(transition_allowed("pending", "completed"));
+        assert!(transition_allowed("completed", "refunded"));
+        assert!(!transition_allowed("completed", "pending"));
+        assert!(!transition_allowed("refunded", "completed"));
+    }
+
+    #[actix_web::test]
+    async fn create_payment_maps_provider_status() {
+        std::env::set_var("JWT_SECRET", JWT);
+        let store: PaymentsStore = Arc::new(Mutex::new(Vec::new()));
+        let provider: Provider = Arc::new(provider::MockAdapter {
+            next_status: "completed".to_string(),
+            reference:   Some("r1".to_string()),
+        });
+        let idem: Idempotency = Arc::new(IdempotencyStore::from_env());
+        let publisher = EventPublisher::start(16, counter("drop1"));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(store.clone()))
+                .app_data(web::Data::new(counter("pay1")))
+                .app_data(web::Data::new(provider))
+                .app_data(web::Data::new(idem))
+                .app_data(web::Data::new(publisher))
+                .route("/payments", web::post().to(create_payment)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/payments")
+            .insert_header(("Authorization", format!("Bearer {}", token(42, None))))
+            .set_json(serde_json::json!({"order_id": 7, "amount": "12.34", "currency": "USD"}))
+            .to_request();
+        let body: Payment = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(body.status, "completed");
+        assert_eq!(body.amount, dec!(12.34));
+        assert_eq!(store.lock().await.len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn webhook_transitions_pending_to_completed() {
+        std::env::set_var("WEBHOOK_SECRET", HOOK);
+        let store = seed(sample("pending"));
+        let webhook_counter =
+            prometheus::CounterVec::new(Opts::new("wh1", "test"), &["status"]).unwrap();
+        let provider: Provider = Arc::new(provider::MockAdapter {
+            next_status: "completed".to_string(),
+            reference:   None,
+        });
+        let publisher = EventPublisher::start(16, counter("drop2"));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(store.clone()))
+                .app_data(web::Data::new(webhook_counter))
+                .app_data(web::Data::new(provider))
+                .app_data(web::Data::new(publisher))
+                .route("/payments/webhook", web::post().to(provider_webhook)),
+        )
+        .await;
+
+        let body = serde_json::to_vec(&serde_json::json!({"reference": "r1", "status": "success"})).unwrap();
+        let req = test::TestRequest::post()
+            .uri("/payments/webhook")
+            .insert_header(("X-Signature", sign(&body)))
+            .insert_header(("content-type", "application/json"))
+            .set_payload(body)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert!(resp.status().is_success());
+        assert_eq!(store.lock().await[0].status, "completed");
+    }
+
+    #[actix_web::test]
+    async fn webhook_rejects_bad_signature() {
+        std::env::set_var("WEBHOOK_SECRET", HOOK);
+        let store = seed(sample("pending"));
+        let webhook_counter =
+            prometheus::CounterVec::new(Opts::new("wh2", "test"), &["status"]).unwrap();
+        let provider: Provider = Arc::new(provider::MockAdapter {
+            next_status: "completed".to_string(),
+            reference:   None,
+        });
+        let publisher = EventPublisher::start(16, counter("drop3"));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(store.clone()))
+                .app_data(web::Data::new(webhook_counter))
+                .app_data(web::Data::new(provider))
+                .app_data(web::Data::new(publisher))
+                .route("/payments/webhook", web::post().to(provider_webhook)),
+        )
+        .await;
+
+        let body = serde_json::to_vec(&serde_json::json!({"reference": "r1", "status": "success"})).unwrap();
+        let req = test::TestRequest::post()
+            .uri("/payments/webhook")
+            .insert_header(("X-Signature", "deadbeef"))
+            .insert_header(("content-type", "application/json"))
+            .set_payload(body)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+        assert_eq!(store.lock().await[0].status, "pending");
+    }
+
+    #[actix_web::test]
+    async fn refund_is_gated_on_admin_role() {
+        std::env::set_var("JWT_SECRET", JWT);
+        let store = seed(sample("completed"));
+        let provider: Provider = Arc::new(provider::MockAdapter {
+            next_status: "completed".to_string(),
+            reference:   None,
+        });
+        let publisher = EventPublisher::start(16, counter("drop4"));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(store.clone()))
+                .app_data(web::Data::new(provider))
+                .app_data(web::Data::new(publisher))
+                .route("/payments/{id}/refund", web::post().to(refund_payment)),
+        )
+        .await;
+
+        // A customer token must be rejected.
+        let req = test::TestRequest::post()
+            .uri("/payments/p1/refund")
+            .insert_header(("Authorization", format!("Bearer {}", token(42, None))))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::FORBIDDEN);
+        assert_eq!(store.lock().await[0].status, "completed");
+
+        // An admin token succeeds and the payment is refunded.
+        let req = test::TestRequest::post()
+            .uri("/payments/p1/refund")
+            .insert_header(("Authorization", format!("Bearer {}", token(1, Some("admin")))))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        assert_eq!(store.lock().await[0].status, "refunded");
+    }
+}
@@ -0,0 +1,48 @@
+// ============================================================
+// src/money.rs — fixed-precision monetary helpers
+// ============================================================
+// WHY not f64?
+//   - f64 cannot represent most decimal fractions exactly, so totals drift
+//     by fractions of a cent and can misrepresent what the customer is
+//     charged. On the payment critical path that is a correctness bug, not
+//     a rounding nicety.
+//   - rust_decimal::Decimal stores base-10 values exactly, which is what
+//     money is.
+//
+// Provider APIs, on the other hand, almost always want the *minor unit*
+// (cents) as an integer, so we convert once, here, rather than sprinkling
+// `* 100.0` across the adapters.
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+/// Number of fractional digits (minor units) a currency uses.
+/// Most currencies use 2; a handful (JPY, KRW, ...) use 0.
+pub fn minor_units(currency: &str) -> u32 {
+    match currency.to_uppercase().as_str() {
+        "JPY" | "KRW" | "VND" | "CLP" | "ISK" => 0,
+        "BHD" | "KWD" | "OMR" | "TND" => 3,
+        _ => 2,
+    }
+}
+
+/// Validate that `amount` is positive and does not carry more fractional
+/// digits than its currency supports (e.g. `1.234 USD` is rejected).
+pub fn validate_amount(amount: Decimal, currency: &str) -> Result<(), String> {
+    if amount <= Decimal::ZERO {
+        return Err("Amount must be positive".to_string());
+    }
+    if amount.scale() > minor_units(currency) {
+        return Err(format!(
+            "Amount has more fractional digits than {currency} supports"
+        ));
+    }
+    Ok(())
+}
+
+/// Convert a decimal amount into the integer minor-unit representation most
+/// provider APIs expect, e.g. `12.34 USD` -> `1234`, `100 JPY` -> `100`.
+pub fn to_minor_units(amount: Decimal, currency: &str) -> i64 {
+    let scaled = amount * Decimal::from(10_i64.pow(minor_units(currency)));
+    scaled.round().to_i64().unwrap_or(0)
+}
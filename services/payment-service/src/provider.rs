@@ -0,0 +1,377 @@
+// ============================================================
+// src/provider.rs — pluggable payment-provider connectors
+// ============================================================
+// WHY a trait + adapter-per-connector?
+//   - create_payment should not care *which* processor runs the charge;
+//     it only speaks the domain language (authorize / capture / refund).
+//   - Each processor (Stripe, PayU, ...) has its own HTTP shape, auth and
+//     status vocabulary. Isolating that behind an adapter keeps the ugly
+//     mapping code in one place per connector instead of bleeding into the
+//     handler.
+//   - The selected provider is chosen once at startup (PAYMENT_PROVIDER)
+//     and stored in application state, so swapping processors is a config
+//     change, not a code change.
+//
+// This mirrors the adapter-per-connector design used in larger Rust payment
+// routers and keeps the service testable with a MockAdapter (see tests).
+
+use crate::money;
+use crate::token::AccessTokenManager;
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use std::env;
+
+// ── Connector errors ──────────────────────────────────────────
+// Kept deliberately small: the handler only needs to know *that* a charge
+// failed and roughly why, not every provider-specific error code.
+#[derive(Debug)]
+pub enum ProviderError {
+    /// The provider was reachable but declined or failed the operation.
+    Declined(String),
+    /// Transport/serialization failure talking to the provider.
+    Transport(String),
+    /// The provider's token endpoint rejected our credentials. Kept separate
+    /// from Declined so auth/session failures are observable on their own.
+    AuthFailed(String),
+}
+
+impl std::fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProviderError::Declined(m) => write!(f, "provider declined: {m}"),
+            ProviderError::Transport(m) => write!(f, "provider transport error: {m}"),
+            ProviderError::AuthFailed(m) => write!(f, "provider auth failed: {m}"),
+        }
+    }
+}
+
+impl std::error::Error for ProviderError {}
+
+// ── Connector request/response ────────────────────────────────
+// These are the connector-facing shapes, intentionally separate from the
+// HTTP-facing PaymentRequest/Payment so the two can evolve independently.
+
+#[derive(Debug, Clone)]
+pub struct AuthorizeRequest {
+    pub order_id: i64,
+    pub user_id:  i64,
+    pub amount:   Decimal,
+    pub currency: String,
+    /// Where the provider should POST its asynchronous status notification.
+    pub notify_uri:   String,
+    /// Where to send the customer back to after the provider-hosted flow.
+    pub continue_uri: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct AuthorizeResponse {
+    /// Mapped into Payment.status: pending | completed | failed | refunded.
+    pub status: String,
+    /// Provider-side reference for the authorization, when returned.
+    pub reference: Option<String>,
+}
+
+// ── Provider trait ────────────────────────────────────────────
+#[async_trait]
+pub trait PaymentProvider: Send + Sync {
+    /// Human-readable connector name, for logs and metrics.
+    fn name(&self) -> &'static str;
+
+    /// Authorize (and, for the simple flow, capture) a charge.
+    async fn authorize(&self, req: &AuthorizeRequest) -> Result<AuthorizeResponse, ProviderError>;
+
+    /// Capture a previously authorized charge by provider reference.
+    async fn capture(&self, reference: &str) -> Result<AuthorizeResponse, ProviderError>;
+
+    /// Refund a captured charge by provider reference.
+    async fn refund(&self, reference: &str) -> Result<AuthorizeResponse, ProviderError>;
+}
+
+// ── Stripe adapter ────────────────────────────────────────────
+pub struct StripeAdapter {
+    client:  reqwest::Client,
+    base_url: String,
+    secret_key: String,
+}
+
+impl StripeAdapter {
+    pub fn from_env() -> Self {
+        StripeAdapter {
+            client: reqwest::Client::new(),
+            base_url: env::var("STRIPE_BASE_URL")
+                .unwrap_or_else(|_| "https://api.stripe.com".to_string()),
+            secret_key: env::var("STRIPE_SECRET_KEY").unwrap_or_default(),
+        }
+    }
+
+    /// Map Stripe's PaymentIntent status onto our Payment.status vocabulary.
+    fn map_status(stripe_status: &str) -> String {
+        match stripe_status {
+            "succeeded" => "completed",
+            "processing" | "requires_capture" => "pending",
+            "canceled" => "refunded",
+            _ => "failed",
+        }
+        .to_string()
+    }
+}
+
+#[async_trait]
+impl PaymentProvider for StripeAdapter {
+    fn name(&self) -> &'static str {
+        "stripe"
+    }
+
+    async fn authorize(&self, req: &AuthorizeRequest) -> Result<AuthorizeResponse, ProviderError> {
+        // Stripe works in the currency's minor unit (cents).
+        let minor = money::to_minor_units(req.amount, &req.currency);
+        let resp = self
+            .client
+            .post(format!("{}/v1/payment_intents", self.base_url))
+            .bearer_auth(&self.secret_key)
+            .form(&[
+                ("amount", minor.to_string()),
+                ("currency", req.currency.to_lowercase()),
+                ("confirm", "true".to_string()),
+                ("metadata[order_id]", req.order_id.to_string()),
+                ("return_url", req.continue_uri.clone()),
+            ])
+            .send()
+            .await
+            .map_err(|e| ProviderError::Transport(e.to_string()))?;
+
+        let body: serde_json::Value = resp
+            .json()
+            .await
+            .map_err(|e| ProviderError::Transport(e.to_string()))?;
+
+        let status = body
+            .get("status")
+            .and_then(|s| s.as_str())
+            .map(StripeAdapter::map_status)
+            .unwrap_or_else(|| "failed".to_string());
+        let reference = body
+            .get("id")
+            .and_then(|s| s.as_str())
+            .map(str::to_string);
+
+        Ok(AuthorizeResponse { status, reference })
+    }
+
+    async fn capture(&self, reference: &str) -> Result<AuthorizeResponse, ProviderError> {
+        let resp = self
+            .client
+            .post(format!("{}/v1/payment_intents/{reference}/capture", self.base_url))
+            .bearer_auth(&self.secret_key)
+            .send()
+            .await
+            .map_err(|e| ProviderError::Transport(e.to_string()))?;
+        let body: serde_json::Value = resp
+            .json()
+            .await
+            .map_err(|e| ProviderError::Transport(e.to_string()))?;
+        let status = body
+            .get("status")
+            .and_then(|s| s.as_str())
+            .map(StripeAdapter::map_status)
+            .unwrap_or_else(|| "failed".to_string());
+        Ok(AuthorizeResponse { status, reference: Some(reference.to_string()) })
+    }
+
+    async fn refund(&self, reference: &str) -> Result<AuthorizeResponse, ProviderError> {
+        let resp = self
+            .client
+            .post(format!("{}/v1/refunds", self.base_url))
+            .bearer_auth(&self.secret_key)
+            .form(&[("payment_intent", reference)])
+            .send()
+            .await
+            .map_err(|e| ProviderError::Transport(e.to_string()))?;
+        if !resp.status().is_success() {
+            return Err(ProviderError::Declined(format!("refund http {}", resp.status())));
+        }
+        Ok(AuthorizeResponse { status: "refunded".to_string(), reference: Some(reference.to_string()) })
+    }
+}
+
+// ── PayU adapter ──────────────────────────────────────────────
+pub struct PayUAdapter {
+    client:   reqwest::Client,
+    base_url: String,
+    tokens:   AccessTokenManager,
+}
+
+impl PayUAdapter {
+    pub fn from_env() -> Self {
+        PayUAdapter {
+            client: reqwest::Client::new(),
+            base_url: env::var("PAYU_BASE_URL")
+                .unwrap_or_else(|_| "https://secure.payu.com".to_string()),
+            tokens: AccessTokenManager::from_env(),
+        }
+    }
+
+    /// Map PayU's orderStatus onto our Payment.status vocabulary.
+    fn map_status(payu_status: &str) -> String {
+        match payu_status {
+            "COMPLETED" => "completed",
+            "PENDING" | "WAITING_FOR_CONFIRMATION" => "pending",
+            "CANCELED" => "refunded",
+            _ => "failed",
+        }
+        .to_string()
+    }
+}
+
+#[async_trait]
+impl PaymentProvider for PayUAdapter {
+    fn name(&self) -> &'static str {
+        "payu"
+    }
+
+    async fn authorize(&self, req: &AuthorizeRequest) -> Result<AuthorizeResponse, ProviderError> {
+        let token = self.tokens.token().await?;
+        // PayU quotes amounts in the minor unit as a string.
+        let minor = money::to_minor_units(req.amount, &req.currency);
+        let resp = self
+            .client
+            .post(format!("{}/api/v2_1/orders", self.base_url))
+            .bearer_auth(&token)
+            .json(&serde_json::json!({
+                "extOrderId": req.order_id.to_string(),
+                "currencyCode": req.currency,
+                "totalAmount": minor.to_string(),
+                "description": format!("order {}", req.order_id),
+                "notifyUrl": req.notify_uri,
+                "continueUrl": req.continue_uri,
+            }))
+            .send()
+            .await
+            .map_err(|e| ProviderError::Transport(e.to_string()))?;
+
+        let body: serde_json::Value = resp
+            .json()
+            .await
+            .map_err(|e| ProviderError::Transport(e.to_string()))?;
+
+        let status = body
+            .pointer("/status/statusCode")
+            .and_then(|s| s.as_str())
+            .map(|c| if c == "SUCCESS" { "pending".to_string() } else { "failed".to_string() })
+            .unwrap_or_else(|| "failed".to_string());
+        let reference = body.get("orderId").and_then(|s| s.as_str()).map(str::to_string);
+
+        Ok(AuthorizeResponse { status, reference })
+    }
+
+    async fn capture(&self, reference: &str) -> Result<AuthorizeResponse, ProviderError> {
+        let token = self.tokens.token().await?;
+        let resp = self
+            .client
+            .put(format!("{}/api/v2_1/orders/{reference}/status", self.base_url))
+            .bearer_auth(&token)
+            .json(&serde_json::json!({ "orderStatus": "COMPLETED" }))
+            .send()
+            .await
+            .map_err(|e| ProviderError::Transport(e.to_string()))?;
+        if !resp.status().is_success() {
+            return Err(ProviderError::Declined(format!("capture http {}", resp.status())));
+        }
+        Ok(AuthorizeResponse { status: "completed".to_string(), reference: Some(reference.to_string()) })
+    }
+
+    async fn refund(&self, reference: &str) -> Result<AuthorizeResponse, ProviderError> {
+        let token = self.tokens.token().await?;
+        let resp = self
+            .client
+            .post(format!("{}/api/v2_1/orders/{reference}/refunds", self.base_url))
+            .bearer_auth(&token)
+            .json(&serde_json::json!({ "refund": { "description": "refund" } }))
+            .send()
+            .await
+            .map_err(|e| ProviderError::Transport(e.to_string()))?;
+        if !resp.status().is_success() {
+            return Err(ProviderError::Declined(format!("refund http {}", resp.status())));
+        }
+        Ok(AuthorizeResponse { status: "refunded".to_string(), reference: Some(reference.to_string()) })
+    }
+}
+
+// ── Provider selection ────────────────────────────────────────
+// Resolve the connector once at startup from PAYMENT_PROVIDER, defaulting
+// to Stripe. Unknown values fall back to Stripe with a warning rather than
+// crashing the service on a typo'd env var.
+pub fn from_env() -> Box<dyn PaymentProvider> {
+    match env::var("PAYMENT_PROVIDER").unwrap_or_default().to_lowercase().as_str() {
+        "payu" => Box::new(PayUAdapter::from_env()),
+        "stripe" | "" => Box::new(StripeAdapter::from_env()),
+        other => {
+            log::warn!("[payment] unknown PAYMENT_PROVIDER '{other}', defaulting to stripe");
+            Box::new(StripeAdapter::from_env())
+        }
+    }
+}
+
+// ── Test connector ────────────────────────────────────────────
+// In-memory connector so handlers can be unit-tested without the network.
+#[cfg(test)]
+pub struct MockAdapter {
+    pub next_status: String,
+    pub reference:   Option<String>,
+}
+
+#[cfg(test)]
+#[async_trait]
+impl PaymentProvider for MockAdapter {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+    async fn authorize(&self, _req: &AuthorizeRequest) -> Result<AuthorizeResponse, ProviderError> {
+        Ok(AuthorizeResponse { status: self.next_status.clone(), reference: self.reference.clone() })
+    }
+    async fn capture(&self, reference: &str) -> Result<AuthorizeResponse, ProviderError> {
+        Ok(AuthorizeResponse { status: "completed".to_string(), reference: Some(reference.to_string()) })
+    }
+    async fn refund(&self, reference: &str) -> Result<AuthorizeResponse, ProviderError> {
+        Ok(AuthorizeResponse { status: "refunded".to_string(), reference: Some(reference.to_string()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn stripe_maps_provider_statuses() {
+        assert_eq!(StripeAdapter::map_status("succeeded"), "completed");
+        assert_eq!(StripeAdapter::map_status("requires_capture"), "pending");
+        assert_eq!(StripeAdapter::map_status("canceled"), "refunded");
+        assert_eq!(StripeAdapter::map_status("anything_else"), "failed");
+    }
+
+    #[test]
+    fn payu_maps_provider_statuses() {
+        assert_eq!(PayUAdapter::map_status("COMPLETED"), "completed");
+        assert_eq!(PayUAdapter::map_status("PENDING"), "pending");
+        assert_eq!(PayUAdapter::map_status("CANCELED"), "refunded");
+        assert_eq!(PayUAdapter::map_status("REJECTED"), "failed");
+    }
+
+    #[tokio::test]
+    async fn mock_adapter_round_trips_lifecycle() {
+        let adapter = MockAdapter { next_status: "completed".to_string(), reference: Some("ref_1".to_string()) };
+        let req = AuthorizeRequest {
+            order_id: 1,
+            user_id: 2,
+            amount: dec!(9.99),
+            currency: "USD".to_string(),
+            notify_uri: "http://x/webhook".to_string(),
+            continue_uri: "http://x/done".to_string(),
+        };
+        let authed = adapter.authorize(&req).await.unwrap();
+        assert_eq!(authed.status, "completed");
+        assert_eq!(adapter.capture("ref_1").await.unwrap().status, "completed");
+        assert_eq!(adapter.refund("ref_1").await.unwrap().status, "refunded");
+    }
+}
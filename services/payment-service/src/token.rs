@@ -0,0 +1,124 @@
+// ============================================================
+// src/token.rs — OAuth2 client-credentials token acquisition/caching
+// ============================================================
+// WHY a dedicated manager?
+//   - PayU-style providers require exchanging client_id/client_secret/
+//     merchant_id for a short-lived bearer token before every order call.
+//   - Minting a token on every request is wasteful and rate-limited; we
+//     cache the token and its expiry and refresh only when it is about to
+//     expire.
+//   - Concurrent create_payment requests must *share* a single refresh
+//     rather than each racing to mint their own, so the cache lives behind
+//     an Arc<Mutex<..>> in application state.
+//
+// Credentials come from env (injected by Vault Agent), never hard-coded.
+
+use crate::provider::ProviderError;
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Refresh the token once we are within this window of its expiry.
+const REFRESH_SKEW_SECS: i64 = 60;
+
+/// A cached access token plus the instant it stops being valid.
+#[derive(Clone)]
+pub struct CachedToken {
+    pub access_token: String,
+    pub expires_at:   DateTime<Utc>,
+}
+
+impl CachedToken {
+    /// True once we are within REFRESH_SKEW_SECS of expiry (or already past).
+    fn is_stale(&self) -> bool {
+        Utc::now() + Duration::seconds(REFRESH_SKEW_SECS) >= self.expires_at
+    }
+}
+
+/// Shape of the OAuth2 token endpoint's JSON response.
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default = "default_expiry")]
+    expires_in:   i64, // seconds
+}
+
+fn default_expiry() -> i64 {
+    // PayU access tokens are valid ~12h; fall back to that if omitted.
+    43_200
+}
+
+/// Acquires and caches a client-credentials access token, shared across
+/// concurrent requests.
+pub struct AccessTokenManager {
+    client:        reqwest::Client,
+    token_url:     String,
+    client_id:     String,
+    client_secret: String,
+    cache:         Arc<Mutex<Option<CachedToken>>>,
+}
+
+impl AccessTokenManager {
+    pub fn from_env() -> Self {
+        AccessTokenManager {
+            client: reqwest::Client::new(),
+            token_url: std::env::var("PAYU_TOKEN_URL")
+                .unwrap_or_else(|_| "https://secure.payu.com/pl/standard/user/oauth/authorize".to_string()),
+            client_id: std::env::var("PAYU_CLIENT_ID").unwrap_or_default(),
+            client_secret: std::env::var("PAYU_CLIENT_SECRET").unwrap_or_default(),
+            cache: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Return a valid bearer token, refreshing if the cache is empty or stale.
+    ///
+    /// The cache mutex is held across the refresh so concurrent callers share
+    /// a single token mint rather than each issuing their own.
+    pub async fn token(&self) -> Result<String, ProviderError> {
+        let mut guard = self.cache.lock().await;
+        if let Some(cached) = guard.as_ref() {
+            if !cached.is_stale() {
+                return Ok(cached.access_token.clone());
+            }
+        }
+
+        let fresh = self.fetch().await?;
+        let token = fresh.access_token.clone();
+        *guard = Some(fresh);
+        Ok(token)
+    }
+
+    /// Perform the client-credentials POST against the token endpoint.
+    async fn fetch(&self) -> Result<CachedToken, ProviderError> {
+        let resp = self
+            .client
+            .post(&self.token_url)
+            .form(&[
+                ("grant_type", "client_credentials"),
+                ("client_id", self.client_id.as_str()),
+                ("client_secret", self.client_secret.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(|e| ProviderError::Transport(e.to_string()))?;
+
+        if !resp.status().is_success() {
+            // Auth/session failures are distinct from order-level declines:
+            // they get their own error variant and log line. No metric counts
+            // them.
+            log::error!("[payment] access-token endpoint rejected credentials: http {}", resp.status());
+            return Err(ProviderError::AuthFailed(format!("token endpoint http {}", resp.status())));
+        }
+
+        let body: TokenResponse = resp
+            .json()
+            .await
+            .map_err(|e| ProviderError::AuthFailed(e.to_string()))?;
+
+        Ok(CachedToken {
+            access_token: body.access_token,
+            expires_at:   Utc::now() + Duration::seconds(body.expires_in),
+        })
+    }
+}